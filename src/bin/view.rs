@@ -0,0 +1,189 @@
+use std::{fs::File, path::PathBuf};
+
+use clap::Parser;
+use macroquad::prelude::*;
+use slider::game::{
+    apply_move, cells_at, try_move, Direction, GameConfiguration, GameSpecification,
+    GoalSpecification, GraphGenerator, Move,
+};
+
+const CELL_SIZE: f32 = 64.0;
+const MARGIN: f32 = 32.0;
+
+/// Default piece colors, used when a piece doesn't specify its own `color`.
+const DEFAULT_PALETTE: &[Color] = &[RED, BLUE, GREEN, ORANGE, PURPLE, SKYBLUE, PINK, LIME];
+
+#[derive(Parser)]
+struct Opts {
+    filename: PathBuf,
+
+    /// Step through the BFS solution (Right/N forward, Left/P back) instead of letting the
+    /// player move pieces.
+    #[arg(long)]
+    solve: bool,
+}
+
+fn piece_color(spec: &GameSpecification, piece_idx: usize) -> Color {
+    spec.pieces[piece_idx]
+        .color
+        .as_deref()
+        .and_then(parse_hex_color)
+        .unwrap_or(DEFAULT_PALETTE[piece_idx % DEFAULT_PALETTE.len()])
+}
+
+fn parse_hex_color(s: &str) -> Option<Color> {
+    let s = s.strip_prefix('#').unwrap_or(s);
+    if s.len() != 6 {
+        return None;
+    }
+
+    let r = u8::from_str_radix(&s[0..2], 16).ok()?;
+    let g = u8::from_str_radix(&s[2..4], 16).ok()?;
+    let b = u8::from_str_radix(&s[4..6], 16).ok()?;
+
+    Some(Color::from_rgba(r, g, b, 255))
+}
+
+fn board_origin() -> Vec2 {
+    vec2(MARGIN, MARGIN)
+}
+
+fn cell_rect(origin: Vec2, position: (usize, usize), size: (usize, usize)) -> Rect {
+    Rect::new(
+        origin.x + position.0 as f32 * CELL_SIZE,
+        origin.y + position.1 as f32 * CELL_SIZE,
+        size.0 as f32 * CELL_SIZE,
+        size.1 as f32 * CELL_SIZE,
+    )
+}
+
+fn goal_cell(spec: &GameSpecification) -> (usize, usize) {
+    let GoalSpecification::Position(target) = &spec.goal;
+    *target
+}
+
+/// Finds the topmost piece with a cell under `point`, if any.
+fn piece_at(
+    spec: &GameSpecification,
+    configuration: &GameConfiguration,
+    origin: Vec2,
+    point: Vec2,
+) -> Option<usize> {
+    (0..spec.pieces.len()).rev().find(|&idx| {
+        cells_at(&spec.pieces[idx], configuration.positions[idx])
+            .into_iter()
+            .any(|cell| cell_rect(origin, cell, (1, 1)).contains(point))
+    })
+}
+
+fn draw_board(spec: &GameSpecification, configuration: &GameConfiguration, selected: Option<usize>) {
+    let origin = board_origin();
+
+    let board_rect = Rect::new(
+        origin.x,
+        origin.y,
+        spec.dimensions.0 as f32 * CELL_SIZE,
+        spec.dimensions.1 as f32 * CELL_SIZE,
+    );
+    draw_rectangle(board_rect.x, board_rect.y, board_rect.w, board_rect.h, DARKGRAY);
+
+    let goal_rect = cell_rect(origin, goal_cell(spec), (1, 1));
+    draw_rectangle(goal_rect.x, goal_rect.y, goal_rect.w, goal_rect.h, GOLD);
+
+    for piece_idx in 0..spec.pieces.len() {
+        let color = piece_color(spec, piece_idx);
+
+        for cell in cells_at(&spec.pieces[piece_idx], configuration.positions[piece_idx]) {
+            let rect = cell_rect(origin, cell, (1, 1));
+            draw_rectangle(rect.x, rect.y, rect.w, rect.h, color);
+
+            if selected == Some(piece_idx) {
+                draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, 4.0, WHITE);
+            }
+        }
+    }
+}
+
+#[macroquad::main("Slider")]
+async fn main() {
+    let opts = Opts::parse();
+
+    let spec: GameSpecification =
+        serde_json::from_reader(File::open(&opts.filename).expect("failed to open level file"))
+            .expect("failed to parse level file");
+
+    if opts.solve {
+        let mut generator = GraphGenerator::new(spec).expect("board too large to encode");
+        let moves: Vec<Move> = generator
+            .solve_bfs()
+            .expect("solve failed")
+            .unwrap_or_default();
+        let spec = generator.specification();
+
+        let mut configurations = vec![spec.as_configuration()];
+        for &mv in &moves {
+            configurations.push(apply_move(configurations.last().unwrap(), mv));
+        }
+
+        let mut step = 0usize;
+
+        loop {
+            clear_background(BLACK);
+
+            if is_key_pressed(KeyCode::Right) || is_key_pressed(KeyCode::N) {
+                step = (step + 1).min(configurations.len() - 1);
+            }
+            if is_key_pressed(KeyCode::Left) || is_key_pressed(KeyCode::P) {
+                step = step.saturating_sub(1);
+            }
+
+            draw_board(spec, &configurations[step], None);
+            draw_text(
+                format!("Move {}/{}", step, moves.len()),
+                MARGIN,
+                20.0,
+                24.0,
+                WHITE,
+            );
+
+            next_frame().await
+        }
+    }
+
+    let mut configuration = spec.as_configuration();
+    let mut selected: Option<usize> = None;
+
+    loop {
+        clear_background(BLACK);
+
+        let origin = board_origin();
+        if is_mouse_button_pressed(MouseButton::Left) {
+            let (mx, my) = mouse_position();
+            selected = piece_at(&spec, &configuration, origin, vec2(mx, my));
+        }
+
+        if let Some(piece_idx) = selected {
+            let direction = if is_key_pressed(KeyCode::Left) {
+                Some(Direction::Left)
+            } else if is_key_pressed(KeyCode::Right) {
+                Some(Direction::Right)
+            } else if is_key_pressed(KeyCode::Up) {
+                Some(Direction::Up)
+            } else if is_key_pressed(KeyCode::Down) {
+                Some(Direction::Down)
+            } else {
+                None
+            };
+
+            if let Some(direction) = direction {
+                if let Some(next) = try_move(&spec, &configuration, piece_idx, direction) {
+                    configuration = next;
+                }
+            }
+        }
+
+        draw_board(&spec, &configuration, selected);
+
+        next_frame().await
+    }
+}
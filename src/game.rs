@@ -1,7 +1,15 @@
-use std::{collections::BTreeSet, fmt::Debug, hash::Hash};
+use std::{
+    cmp::Reverse,
+    collections::{BTreeSet, BinaryHeap, HashMap, VecDeque},
+    fmt::Debug,
+    hash::Hash,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 use anyhow::Result;
-use serde::Deserialize;
-use crate::bidirectional_list::BidirectionalList;
+use dashmap::{DashMap, DashSet};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
 
 #[derive(Deserialize, Debug)]
 pub enum GoalSpecification {
@@ -9,27 +17,235 @@ pub enum GoalSpecification {
     Position((usize, usize)),
 }
 
+/// The direction a piece moved between two consecutive configurations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+/// A single slide: which piece moved, and which way.
+pub type Move = (usize, Direction);
+
+/// Applies a single move to a configuration, producing the configuration it leads to.
+/// The inverse of `diff_move`; lets callers (e.g. the viewer) replay a solution's moves
+/// one at a time without re-deriving the full configuration sequence from the solver.
+pub fn apply_move(configuration: &GameConfiguration, (piece_idx, direction): Move) -> GameConfiguration {
+    let mut configuration = configuration.clone();
+    let (x, y) = configuration.positions[piece_idx];
+
+    configuration.positions[piece_idx] = match direction {
+        Direction::Left => (x - 1, y),
+        Direction::Right => (x + 1, y),
+        Direction::Up => (x, y - 1),
+        Direction::Down => (x, y + 1),
+    };
+
+    configuration
+}
+
 #[derive(Deserialize, Debug)]
 pub struct PieceSpecification {
-    size: (usize, usize),
+    /// This piece's rectangle size, used when `segments` is absent. Only required for
+    /// rectangular pieces; a piece defined purely via `segments` can omit it.
+    #[serde(default)]
+    pub size: Option<(usize, usize)>,
+    pub position: (usize, usize),
+    pub moves: (bool, bool),
+    /// Display color for this piece, e.g. `"#ff0000"`. Purely cosmetic; unused outside the viewer.
+    #[serde(default)]
+    pub color: Option<String>,
+    /// Cell offsets (from `position`) this piece occupies, for L-shaped and other
+    /// non-rectangular pieces. When absent, the piece is the `size.0` x `size.1` rectangle.
+    #[serde(default)]
+    pub segments: Option<Vec<(usize, usize)>>,
+}
+
+impl PieceSpecification {
+    /// The cell offsets (relative to `position`) this piece occupies.
+    pub fn cells(&self) -> Vec<(usize, usize)> {
+        match &self.segments {
+            Some(segments) => segments.clone(),
+            None => {
+                let size = self
+                    .size
+                    .expect("piece specifies neither `segments` nor `size`");
+                (0..size.0)
+                    .flat_map(|x| (0..size.1).map(move |y| (x, y)))
+                    .collect()
+            }
+        }
+    }
+}
+
+/// The absolute cells `piece` occupies when placed at `position`.
+pub fn cells_at(piece: &PieceSpecification, position: (usize, usize)) -> Vec<(usize, usize)> {
+    piece
+        .cells()
+        .into_iter()
+        .map(|(dx, dy)| (position.0 + dx, position.1 + dy))
+        .collect()
+}
+
+/// Shifts `position` one cell in `direction`, or `None` if that would carry any of the
+/// piece's cells off the board.
+fn step(
     position: (usize, usize),
-    moves: (bool, bool),
+    direction: Direction,
+    dimensions: (usize, usize),
+    piece: &PieceSpecification,
+) -> Option<(usize, usize)> {
+    let new_position = match direction {
+        Direction::Left => (position.0.checked_sub(1)?, position.1),
+        Direction::Right => (position.0 + 1, position.1),
+        Direction::Up => (position.0, position.1.checked_sub(1)?),
+        Direction::Down => (position.0, position.1 + 1),
+    };
+
+    cells_at(piece, new_position)
+        .into_iter()
+        .all(|(x, y)| x < dimensions.0 && y < dimensions.1)
+        .then_some(new_position)
+}
+
+/// Slides `piece` one cell in `direction` from `position`, given a board with every
+/// piece's current footprint already placed. Only the cells the piece would newly sweep
+/// into have to be clear, since its own old footprint (part of `board`) is excluded from
+/// the check; this is what makes the collision check work for L-shaped pieces too.
+fn try_step(
+    piece: &PieceSpecification,
+    position: (usize, usize),
+    direction: Direction,
+    dimensions: (usize, usize),
+    board: &GameBoard,
+) -> Option<(usize, usize)> {
+    let new_position = step(position, direction, dimensions, piece)?;
+
+    let old_cells: BTreeSet<(usize, usize)> = cells_at(piece, position).into_iter().collect();
+    let swept: Vec<(usize, usize)> = cells_at(piece, new_position)
+        .into_iter()
+        .filter(|cell| !old_cells.contains(cell))
+        .collect();
+
+    board.is_clear(&swept).then_some(new_position)
 }
 
 #[derive(Deserialize, Debug)]
 pub struct GameSpecification {
-    dimensions: (usize, usize),
-    pieces: Vec<PieceSpecification>,
-    #[allow(unused)]
-    goal: GoalSpecification,
+    pub dimensions: (usize, usize),
+    pub pieces: Vec<PieceSpecification>,
+    pub goal: GoalSpecification,
 }
 
-#[derive(Hash, Clone, PartialEq, Eq, Debug)]
+#[derive(Hash, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub struct GameConfiguration {
-    positions: Vec<(usize, usize)>,
+    pub positions: Vec<(usize, usize)>,
+}
+
+/// Number of bits needed to represent any value in `0..n`, i.e. `ceil(log2(n))` (with a
+/// floor of 1 bit, since a board dimension of 1 still needs a slot to store the index 0).
+fn bits_for(n: usize) -> u32 {
+    match n {
+        0 | 1 => 1,
+        n => (usize::BITS - (n - 1).leading_zeros()).max(1),
+    }
+}
+
+/// Stores `GameConfiguration`s as packed integer keys instead of heap-allocated
+/// `Vec<(usize, usize)>`s. Each piece's position only needs `ceil(log2(dimension))` bits
+/// per axis, so a whole configuration is packed into one `u128` (`new` rejects boards
+/// whose pieces need more bits than that); `keys` then holds one key per node
+/// (replacing the old per-node `Box::leak`, which never freed its allocation) and
+/// `inverse` maps a key back to its node id. Positions are reconstructed on demand via
+/// `get`, since nothing here needs to hold a long-lived reference to them.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ConfigurationTable {
+    bits_per_axis: (u32, u32),
+    piece_count: usize,
+    keys: Vec<u128>,
+    inverse: HashMap<u128, usize>,
+}
+
+impl ConfigurationTable {
+    pub fn new(dimensions: (usize, usize), piece_count: usize) -> Result<Self> {
+        let bits_per_axis = (bits_for(dimensions.0), bits_for(dimensions.1));
+        let total_bits = piece_count as u32 * (bits_per_axis.0 + bits_per_axis.1);
+
+        if total_bits > u128::BITS {
+            anyhow::bail!(
+                "board needs {total_bits} bits to pack every piece's position into one key, \
+                 but a u128 only holds {}; reduce the board size or piece count",
+                u128::BITS
+            );
+        }
+
+        Ok(ConfigurationTable {
+            bits_per_axis,
+            piece_count,
+            keys: Vec::new(),
+            inverse: HashMap::new(),
+        })
+    }
+
+    fn encode(&self, configuration: &GameConfiguration) -> u128 {
+        let (bits_x, bits_y) = self.bits_per_axis;
+        let slot_bits = bits_x + bits_y;
+
+        configuration
+            .positions
+            .iter()
+            .enumerate()
+            .fold(0u128, |key, (piece_idx, &(x, y))| {
+                let slot = (x as u128) | ((y as u128) << bits_x);
+                key | (slot << (piece_idx as u32 * slot_bits))
+            })
+    }
+
+    fn decode(&self, key: u128) -> GameConfiguration {
+        let (bits_x, bits_y) = self.bits_per_axis;
+        let slot_bits = bits_x + bits_y;
+        let slot_mask = (1u128 << slot_bits) - 1;
+        let x_mask = (1u128 << bits_x) - 1;
+
+        let positions = (0..self.piece_count)
+            .map(|piece_idx| {
+                let slot = (key >> (piece_idx as u32 * slot_bits)) & slot_mask;
+                ((slot & x_mask) as usize, (slot >> bits_x) as usize)
+            })
+            .collect();
+
+        GameConfiguration { positions }
+    }
+
+    pub fn push(&mut self, configuration: &GameConfiguration) -> usize {
+        let idx = self.keys.len();
+        let key = self.encode(configuration);
+        self.keys.push(key);
+        self.inverse.insert(key, idx);
+
+        idx
+    }
+
+    pub fn get_index(&self, configuration: &GameConfiguration) -> Option<usize> {
+        self.inverse.get(&self.encode(configuration)).copied()
+    }
+
+    pub fn get(&self, idx: usize) -> Option<GameConfiguration> {
+        self.keys.get(idx).map(|&key| self.decode(key))
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
 }
 
-struct GameBoard {
+pub struct GameBoard {
     bitmap: Vec<bool>,
     width: usize,
     height: usize,
@@ -54,29 +270,20 @@ impl Debug for GameBoard {
 
 impl GameBoard {
     pub fn place(&mut self, piece: &PieceSpecification, position: (usize, usize)) {
-        for x_ in 0..piece.size.0 {
-            let x = x_ + position.0;
-            for y_ in 0..piece.size.1 {
-                let y = y_ + position.1;
-                self.bitmap[x + y * self.width] = true
-            }
+        for (x, y) in cells_at(piece, position) {
+            self.bitmap[x + y * self.width] = true
         }
     }
 
-    pub fn is_rect_clear(&self, position: (usize, usize), size: (usize, usize)) -> bool {
-        for x_ in 0..size.0 {
-            let x = x_ + position.0;
-
-            assert!(x < self.width);
-            for y_ in 0..size.1 {
-                let y = y_ + position.1;
-                if self.bitmap[x + y * self.width] {
-                    return false;
-                }
-            }
-        }
-
-        true
+    /// True if none of `cells` are occupied. Unlike the old rectangle-only check, this
+    /// takes an explicit cell list so callers can pass just the cells a piece would newly
+    /// sweep into, which is what makes the "a piece doesn't collide with its own old
+    /// footprint" trick work for non-rectangular pieces too.
+    pub fn is_clear(&self, cells: &[(usize, usize)]) -> bool {
+        cells.iter().all(|&(x, y)| {
+            assert!(x < self.width && y < self.height);
+            !self.bitmap[x + y * self.width]
+        })
     }
 
     pub fn clear(&mut self) {
@@ -99,32 +306,111 @@ impl GameSpecification {
     }
 }
 
+/// Attempts to slide `piece_idx` one cell in `direction`, honoring `piece.moves` and the
+/// same bounds/collision rules `GraphGenerator` uses to enumerate neighbors. Shared by the
+/// solver and the viewer so the two can't drift apart on what counts as a legal slide.
+pub fn try_move(
+    specification: &GameSpecification,
+    configuration: &GameConfiguration,
+    piece_idx: usize,
+    direction: Direction,
+) -> Option<GameConfiguration> {
+    let piece = &specification.pieces[piece_idx];
+
+    let allowed = match direction {
+        Direction::Left | Direction::Right => piece.moves.0,
+        Direction::Up | Direction::Down => piece.moves.1,
+    };
+    if !allowed {
+        return None;
+    }
+
+    let mut board = GameBoard::new(specification.dimensions);
+    for (other_piece, other_position) in specification.pieces.iter().zip(&configuration.positions) {
+        board.place(other_piece, *other_position);
+    }
+
+    let position = configuration.positions[piece_idx];
+    try_step(piece, position, direction, specification.dimensions, &board)?;
+
+    Some(apply_move(configuration, (piece_idx, direction)))
+}
+
+/// A generated state graph, independent of the `GameSpecification` that produced it, so it
+/// can be serialized and later reloaded without regenerating it from scratch.
+#[derive(Serialize, Deserialize)]
+pub struct GraphData {
+    pub nodes: ConfigurationTable,
+    pub edges: BTreeSet<(usize, usize)>,
+}
+
+impl GraphData {
+    pub fn save(&self, writer: impl Write) -> Result<()> {
+        Ok(serde_json::to_writer(writer, self)?)
+    }
+
+    pub fn load(reader: impl Read) -> Result<Self> {
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    /// Renders the graph as a GraphViz DOT document, with each node labeled by its pieces'
+    /// positions so the state space can be visualized with `dot`/`neato`/etc.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("graph {\n");
+
+        for idx in 0..self.nodes.len() {
+            let configuration = self.nodes.get(idx).unwrap();
+            dot.push_str(&format!(
+                "  {} [label=\"{:?}\"];\n",
+                idx, configuration.positions
+            ));
+        }
+
+        for (a, b) in &self.edges {
+            dot.push_str(&format!("  {} -- {};\n", a, b));
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
 pub struct GraphGenerator {
-    nodes: BidirectionalList<GameConfiguration>,
+    nodes: ConfigurationTable,
     edges: BTreeSet<(usize, usize)>,
     queue: Vec<usize>,
-    board: GameBoard,
     specification: GameSpecification,
 }
 
 impl GraphGenerator {
-    pub fn new(specification: GameSpecification) -> Self {
-        let board = GameBoard::new(specification.dimensions);
-        let mut nodes = BidirectionalList::default();
-        let idx = nodes.push(specification.as_configuration());
+    pub fn new(specification: GameSpecification) -> Result<Self> {
+        let mut nodes = ConfigurationTable::new(specification.dimensions, specification.pieces.len())?;
+        let idx = nodes.push(&specification.as_configuration());
         let queue = vec![idx];
 
-        GraphGenerator {
+        Ok(GraphGenerator {
             specification,
             nodes,
             edges: Default::default(),
             queue,
-            board,
+        })
+    }
+
+    pub fn specification(&self) -> &GameSpecification {
+        &self.specification
+    }
+
+    /// Snapshots the generated graph so it can be serialized independently of this
+    /// generator (and its `GameSpecification`).
+    pub fn graph_data(&self) -> GraphData {
+        GraphData {
+            nodes: self.nodes.clone(),
+            edges: self.edges.clone(),
         }
     }
 
     pub fn enqueue_configuration(
-        nodes: &mut BidirectionalList<GameConfiguration>,
+        nodes: &mut ConfigurationTable,
         queue: &mut Vec<usize>,
         edges: &mut BTreeSet<(usize, usize)>,
         configuration: GameConfiguration,
@@ -134,7 +420,7 @@ impl GraphGenerator {
             // This configuration has already been visited; don't do anything.
             idx
         } else {
-            let idx = nodes.push(configuration);
+            let idx = nodes.push(&configuration);
             queue.push(idx);
 
             idx
@@ -143,103 +429,214 @@ impl GraphGenerator {
         edges.insert((idx.min(neighbor), idx.max(neighbor)));
     }
 
-    pub fn visit_node(&mut self, idx: usize) {
-        let configuration = self.nodes.get(idx).unwrap().clone();
-        self.board.clear();
-
-        for (piece, position) in self
-            .specification
-            .pieces
-            .iter()
-            .zip(&configuration.positions)
-        {
-            self.board.place(piece, *position)
+    /// Computes the configurations reachable from `configuration` by sliding a single piece
+    /// one step. Takes the specification by reference instead of `&self` so it can be called
+    /// from parallel workers that only hold a shared borrow of it.
+    fn compute_neighbors(
+        specification: &GameSpecification,
+        configuration: &GameConfiguration,
+    ) -> Vec<GameConfiguration> {
+        let mut board = GameBoard::new(specification.dimensions);
+
+        for (piece, position) in specification.pieces.iter().zip(&configuration.positions) {
+            board.place(piece, *position)
         }
 
-        assert_eq!(18, self.board.bitmap.iter().filter(|d| **d).count());
+        let mut neighbors = Vec::new();
 
-        for piece_idx in 0..self.specification.pieces.len() {
-            let piece = &self.specification.pieces[piece_idx];
+        for piece_idx in 0..specification.pieces.len() {
+            let piece = &specification.pieces[piece_idx];
             let position = configuration.positions[piece_idx];
 
+            let mut directions = Vec::new();
             if piece.moves.0 {
-                // Horizontal moves allowed
-                let size = (1, piece.size.1);
-
-                if position.0 > 0 && self.board.is_rect_clear((position.0 - 1, position.1), size) {
-                    // this piece can move left
-                    let mut configuration = configuration.clone();
-                    configuration.positions[piece_idx] = (position.0 - 1, position.1);
-                    Self::enqueue_configuration(
-                        &mut self.nodes,
-                        &mut self.queue,
-                        &mut self.edges,
-                        configuration,
-                        idx,
-                    );
-                }
+                directions.push(Direction::Left);
+                directions.push(Direction::Right);
+            }
+            if piece.moves.1 {
+                directions.push(Direction::Up);
+                directions.push(Direction::Down);
+            }
 
-                if position.0 + piece.size.0 < self.board.width
-                    && self
-                        .board
-                        .is_rect_clear((position.0 + piece.size.0, position.1), size)
+            for direction in directions {
+                if let Some(new_position) =
+                    try_step(piece, position, direction, specification.dimensions, &board)
                 {
-                    // this piece can move right
                     let mut configuration = configuration.clone();
-                    configuration.positions[piece_idx] = (position.0 + 1, position.1);
-                    Self::enqueue_configuration(
-                        &mut self.nodes,
-                        &mut self.queue,
-                        &mut self.edges,
-                        configuration,
-                        idx,
-                    );
+                    configuration.positions[piece_idx] = new_position;
+                    neighbors.push(configuration);
                 }
             }
+        }
 
-            if piece.moves.1 {
-                // Vertical moves allowed
-                let size = (piece.size.0, 1);
+        neighbors
+    }
 
-                if position.1 > 0 && self.board.is_rect_clear((position.0, position.1 - 1), size) {
-                    // this piece can move up
-                    let mut configuration = configuration.clone();
-                    configuration.positions[piece_idx] = (position.0, position.1 - 1);
-                    Self::enqueue_configuration(
-                        &mut self.nodes,
-                        &mut self.queue,
-                        &mut self.edges,
-                        configuration,
-                        idx,
-                    );
+    fn neighbors(&self, idx: usize) -> Vec<GameConfiguration> {
+        let configuration = self.nodes.get(idx).unwrap();
+        Self::compute_neighbors(&self.specification, &configuration)
+    }
+
+    pub fn visit_node(&mut self, idx: usize) {
+        for configuration in self.neighbors(idx) {
+            Self::enqueue_configuration(
+                &mut self.nodes,
+                &mut self.queue,
+                &mut self.edges,
+                configuration,
+                idx,
+            );
+        }
+    }
+
+    /// The piece whose position the goal is measured against.
+    ///
+    /// Level files don't (yet) tag a piece as the goal piece, so by convention
+    /// it's the first piece listed, matching the "home piece" of classic
+    /// sliding-block puzzles (e.g. the 2x2 block in Klotski).
+    fn goal_piece_index(&self) -> usize {
+        0
+    }
+
+    fn satisfies_goal(&self, idx: usize) -> bool {
+        let configuration = self.nodes.get(idx).unwrap();
+        let GoalSpecification::Position(target) = &self.specification.goal;
+        configuration.positions[self.goal_piece_index()] == *target
+    }
+
+    /// Diffs two consecutive configurations in a solution path into the single move
+    /// (piece and direction) that separates them.
+    fn diff_move(from: &GameConfiguration, to: &GameConfiguration) -> Move {
+        for (piece_idx, (a, b)) in from.positions.iter().zip(&to.positions).enumerate() {
+            if a != b {
+                let direction = match (b.0 as isize - a.0 as isize, b.1 as isize - a.1 as isize) {
+                    (-1, 0) => Direction::Left,
+                    (1, 0) => Direction::Right,
+                    (0, -1) => Direction::Up,
+                    (0, 1) => Direction::Down,
+                    delta => unreachable!("a single slide can only shift one piece by one cell, got {:?}", delta),
+                };
+                return (piece_idx, direction);
+            }
+        }
+
+        unreachable!("consecutive configurations in a solution path must differ by exactly one move")
+    }
+
+    fn reconstruct_path(&self, predecessors: &[usize], goal_idx: usize) -> Vec<Move> {
+        let mut chain = vec![goal_idx];
+        let mut current = goal_idx;
+
+        while predecessors[current] != current {
+            current = predecessors[current];
+            chain.push(current);
+        }
+
+        chain.reverse();
+
+        chain
+            .windows(2)
+            .map(|pair| {
+                let from = self.nodes.get(pair[0]).unwrap();
+                let to = self.nodes.get(pair[1]).unwrap();
+                Self::diff_move(&from, &to)
+            })
+            .collect()
+    }
+
+    /// Finds the shortest sequence of moves from the starting configuration to the goal
+    /// via breadth-first search, so the first configuration that satisfies the goal is
+    /// guaranteed to be reachable in the minimum number of slides.
+    pub fn solve_bfs(&mut self) -> Result<Option<Vec<Move>>> {
+        let root = 0;
+        let mut predecessors: Vec<usize> = vec![root];
+
+        if self.satisfies_goal(root) {
+            return Ok(Some(Vec::new()));
+        }
+
+        let mut frontier: VecDeque<usize> = VecDeque::new();
+        frontier.push_back(root);
+
+        while let Some(idx) = frontier.pop_front() {
+            for configuration in self.neighbors(idx) {
+                if self.nodes.get_index(&configuration).is_some() {
+                    // Already visited; BFS already found the shortest path to it.
+                    continue;
                 }
 
-                if position.1 + piece.size.1 < self.board.height
-                    && self
-                        .board
-                        .is_rect_clear((position.0, position.1 + piece.size.1), size)
-                {
-                    // this piece can move down
-                    let mut configuration = configuration.clone();
-                    configuration.positions[piece_idx] = (position.0, position.1 + 1);
-                    Self::enqueue_configuration(
-                        &mut self.nodes,
-                        &mut self.queue,
-                        &mut self.edges,
-                        configuration,
-                        idx,
-                    );
+                let child_idx = self.nodes.push(&configuration);
+                predecessors.push(idx);
+
+                if self.satisfies_goal(child_idx) {
+                    return Ok(Some(self.reconstruct_path(&predecessors, child_idx)));
+                }
+
+                frontier.push_back(child_idx);
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Admissible lower bound on the remaining moves: the Manhattan distance from the
+    /// goal piece's current position to its target. Each slide moves a piece exactly one
+    /// cell, so this never overestimates the true remaining distance.
+    fn heuristic(&self, idx: usize) -> usize {
+        let configuration = self.nodes.get(idx).unwrap();
+        let GoalSpecification::Position(target) = &self.specification.goal;
+        let position = configuration.positions[self.goal_piece_index()];
+
+        position.0.abs_diff(target.0) + position.1.abs_diff(target.1)
+    }
+
+    /// Finds the shortest sequence of moves from the starting configuration to the goal
+    /// via A*, expanding nodes in order of `f = g + h` instead of enumerating the full
+    /// reachable graph. This makes boards whose full state space is intractable solvable,
+    /// at the cost of full-graph outputs like `generate`'s edge list.
+    pub fn solve_astar(&mut self) -> Result<Option<Vec<Move>>> {
+        let root = 0;
+        let mut predecessors: Vec<usize> = vec![root];
+        let mut g_score: HashMap<usize, usize> = HashMap::new();
+        g_score.insert(root, 0);
+
+        // Min-heap on (f, g, idx); `Reverse` turns the max-heap `BinaryHeap` into a min-heap.
+        let mut open: BinaryHeap<Reverse<(usize, usize, usize)>> = BinaryHeap::new();
+        open.push(Reverse((self.heuristic(root), 0, root)));
+
+        while let Some(Reverse((_, g, idx))) = open.pop() {
+            if self.satisfies_goal(idx) {
+                return Ok(Some(self.reconstruct_path(&predecessors, idx)));
+            }
+
+            if g > *g_score.get(&idx).unwrap_or(&usize::MAX) {
+                // A cheaper path to this node was already relaxed; this entry is stale.
+                continue;
+            }
+
+            for configuration in self.neighbors(idx) {
+                let child_idx = if let Some(existing) = self.nodes.get_index(&configuration) {
+                    existing
+                } else {
+                    let child_idx = self.nodes.push(&configuration);
+                    predecessors.push(idx);
+                    child_idx
+                };
+
+                let tentative_g = g + 1;
+                if tentative_g < *g_score.get(&child_idx).unwrap_or(&usize::MAX) {
+                    g_score.insert(child_idx, tentative_g);
+                    predecessors[child_idx] = idx;
+                    let f = tentative_g + self.heuristic(child_idx);
+                    open.push(Reverse((f, tentative_g, child_idx)));
                 }
             }
         }
+
+        Ok(None)
     }
 
-    pub fn generate(
-        &mut self,
-    ) -> Result<(
-        &BidirectionalList<GameConfiguration>,
-        &BTreeSet<(usize, usize)>,
-    )> {
+    pub fn generate(&mut self) -> Result<(&ConfigurationTable, &BTreeSet<(usize, usize)>)> {
         let mut step: u32 = 0;
         while !self.queue.is_empty() {
             if step % 1_000_000 == 0 {
@@ -258,4 +655,209 @@ impl GraphGenerator {
 
         Ok((&self.nodes, &self.edges))
     }
+
+    /// Same graph as `generate`, but expands each BFS layer with rayon instead of walking
+    /// the frontier one node at a time. Node ids follow BFS discovery order, so they can be
+    /// numbered differently from `generate`'s. `index`/`by_id` replace `ConfigurationTable`'s
+    /// `inverse` with a `DashMap` so concurrent workers only contend for the shard a key
+    /// hashes to, not a single global lock; `edges` is a `DashSet` for the same reason.
+    /// Every node in a layer computes its neighbors independently into a thread-local `Vec`
+    /// before the (inherently sequential) dedup/merge step, so the expensive part --
+    /// stamping each node's board and testing every candidate slide -- is fully parallel.
+    pub fn generate_parallel(&mut self) -> Result<(&ConfigurationTable, &BTreeSet<(usize, usize)>)> {
+        let root = self.specification.as_configuration();
+
+        let index: DashMap<GameConfiguration, usize> = DashMap::new();
+        let by_id: DashMap<usize, GameConfiguration> = DashMap::new();
+        let edges: DashSet<(usize, usize)> = DashSet::new();
+        let next_id = AtomicUsize::new(0);
+
+        let root_id = next_id.fetch_add(1, Ordering::Relaxed);
+        index.insert(root.clone(), root_id);
+        by_id.insert(root_id, root);
+
+        let mut frontier = vec![root_id];
+        let mut layer: u32 = 0;
+
+        while !frontier.is_empty() {
+            eprintln!(
+                "Layer: {}, frontier size: {}, visited: {}",
+                layer,
+                frontier.len(),
+                by_id.len()
+            );
+            layer += 1;
+
+            let discovered: Vec<(GameConfiguration, usize)> = frontier
+                .par_iter()
+                .flat_map(|&idx| {
+                    let configuration = by_id.get(&idx).unwrap().clone();
+                    Self::compute_neighbors(&self.specification, &configuration)
+                        .into_iter()
+                        .map(move |neighbor| (neighbor, idx))
+                        .collect::<Vec<_>>()
+                })
+                .collect();
+
+            let mut next_frontier = Vec::new();
+
+            for (configuration, parent) in discovered {
+                let child_id = match index.entry(configuration.clone()) {
+                    dashmap::mapref::entry::Entry::Occupied(entry) => *entry.get(),
+                    dashmap::mapref::entry::Entry::Vacant(entry) => {
+                        let id = next_id.fetch_add(1, Ordering::Relaxed);
+                        entry.insert(id);
+                        by_id.insert(id, configuration);
+                        next_frontier.push(id);
+                        id
+                    }
+                };
+
+                edges.insert((parent.min(child_id), parent.max(child_id)));
+            }
+
+            frontier = next_frontier;
+        }
+
+        self.nodes = ConfigurationTable::new(
+            self.specification.dimensions,
+            self.specification.pieces.len(),
+        )?;
+        let mut ids: Vec<usize> = by_id.iter().map(|entry| *entry.key()).collect();
+        ids.sort_unstable();
+
+        for id in ids {
+            let configuration = by_id.get(&id).unwrap().clone();
+            let pushed = self.nodes.push(&configuration);
+            debug_assert_eq!(pushed, id);
+        }
+
+        self.edges = edges.into_iter().collect();
+
+        Ok((&self.nodes, &self.edges))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn small_specification() -> GameSpecification {
+        GameSpecification {
+            dimensions: (4, 4),
+            pieces: vec![
+                PieceSpecification {
+                    size: Some((2, 2)),
+                    position: (0, 0),
+                    moves: (true, true),
+                    color: None,
+                    segments: None,
+                },
+                PieceSpecification {
+                    size: Some((1, 1)),
+                    position: (3, 3),
+                    moves: (true, true),
+                    color: None,
+                    segments: None,
+                },
+            ],
+            goal: GoalSpecification::Position((2, 2)),
+        }
+    }
+
+    #[test]
+    fn bits_for_is_ceil_log2_with_a_floor_of_one() {
+        assert_eq!(bits_for(1), 1);
+        assert_eq!(bits_for(2), 1);
+        assert_eq!(bits_for(3), 2);
+        assert_eq!(bits_for(4), 2);
+        assert_eq!(bits_for(5), 3);
+        assert_eq!(bits_for(16), 4);
+    }
+
+    #[test]
+    fn configuration_table_round_trips_through_push_and_get() {
+        let mut table = ConfigurationTable::new((4, 4), 2).unwrap();
+        let a = GameConfiguration {
+            positions: vec![(0, 0), (3, 3)],
+        };
+        let b = GameConfiguration {
+            positions: vec![(1, 0), (3, 3)],
+        };
+
+        let a_idx = table.push(&a);
+        let b_idx = table.push(&b);
+
+        assert_eq!(table.get(a_idx), Some(a.clone()));
+        assert_eq!(table.get(b_idx), Some(b.clone()));
+        assert_eq!(table.get_index(&a), Some(a_idx));
+        assert_eq!(table.get_index(&b), Some(b_idx));
+    }
+
+    #[test]
+    fn configuration_table_rejects_boards_that_do_not_fit_in_a_key() {
+        // 10x10 needs 4 bits/axis; 20 pieces * 8 bits/piece = 160 bits, more than a u128 holds.
+        assert!(ConfigurationTable::new((10, 10), 20).is_err());
+    }
+
+    #[test]
+    fn diff_move_reports_the_piece_and_direction_that_changed() {
+        let from = GameConfiguration {
+            positions: vec![(0, 0), (3, 3)],
+        };
+        let to = GameConfiguration {
+            positions: vec![(1, 0), (3, 3)],
+        };
+
+        assert_eq!(GraphGenerator::diff_move(&from, &to), (0, Direction::Right));
+    }
+
+    #[test]
+    fn solve_bfs_finds_the_known_optimal_move_count() {
+        let mut generator = GraphGenerator::new(small_specification()).unwrap();
+        let moves = generator.solve_bfs().unwrap().unwrap();
+        assert_eq!(moves.len(), 6);
+    }
+
+    #[test]
+    fn to_dot_labels_every_node_and_edge() {
+        let mut generator = GraphGenerator::new(small_specification()).unwrap();
+        generator.generate().unwrap();
+        let graph_data = generator.graph_data();
+        let dot = graph_data.to_dot();
+
+        assert!(dot.starts_with("graph {\n"));
+        assert!(dot.ends_with("}\n"));
+        assert!(dot.contains("0 [label="));
+        for idx in 0..graph_data.nodes.len() {
+            assert!(dot.contains(&format!("  {} [label=", idx)));
+        }
+        for (a, b) in &graph_data.edges {
+            assert!(dot.contains(&format!("  {} -- {};", a, b)));
+        }
+    }
+
+    #[test]
+    fn solve_astar_matches_solve_bfs_move_count() {
+        let mut bfs_generator = GraphGenerator::new(small_specification()).unwrap();
+        let bfs_moves = bfs_generator.solve_bfs().unwrap().unwrap();
+
+        let mut astar_generator = GraphGenerator::new(small_specification()).unwrap();
+        let astar_moves = astar_generator.solve_astar().unwrap().unwrap();
+
+        assert_eq!(astar_moves.len(), bfs_moves.len());
+    }
+
+    #[test]
+    fn generate_parallel_matches_generate_node_and_edge_counts() {
+        let mut sequential = GraphGenerator::new(small_specification()).unwrap();
+        let (nodes, edges) = sequential.generate().unwrap();
+        let (sequential_nodes, sequential_edges) = (nodes.len(), edges.len());
+
+        let mut parallel = GraphGenerator::new(small_specification()).unwrap();
+        let (nodes, edges) = parallel.generate_parallel().unwrap();
+
+        assert_eq!(nodes.len(), sequential_nodes);
+        assert_eq!(edges.len(), sequential_edges);
+    }
 }
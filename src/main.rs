@@ -1,26 +1,95 @@
-use std::{path::PathBuf, fs::File};
-use clap::Parser;
-use game::{GameSpecification, GraphGenerator};
+use std::{fs::File, path::PathBuf};
+use clap::{Parser, ValueEnum};
+use slider::game::{GameSpecification, GraphData, GraphGenerator};
 use anyhow::Result;
 
-mod bidirectional_list;
-mod game;
+#[derive(Clone, Copy, ValueEnum)]
+enum SearchMode {
+    Bfs,
+    Astar,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Csv,
+    Dot,
+    Json,
+}
 
 #[derive(Parser)]
 struct Opts {
     filename: PathBuf,
+
+    /// Find the shortest move sequence to the goal instead of printing the full state graph.
+    #[arg(long, value_enum)]
+    search: Option<SearchMode>,
+
+    /// Expand the full state graph with rayon instead of one node at a time. Ignored with --search.
+    #[arg(long)]
+    parallel: bool,
+
+    /// How to print the full state graph. Ignored with --search.
+    #[arg(long, value_enum, default_value = "csv")]
+    format: OutputFormat,
+
+    /// Skip regeneration and load a previously-saved graph (see --save) instead.
+    #[arg(long)]
+    load: Option<PathBuf>,
+
+    /// Save the generated graph (as JSON) so a later run can skip regeneration with --load.
+    #[arg(long)]
+    save: Option<PathBuf>,
 }
 
 fn main() -> Result<()> {
     let opts = Opts::parse();
 
-    let game_spec: GameSpecification = serde_json::from_reader(File::open(opts.filename)?)?;
-    let mut graph_generator = GraphGenerator::new(game_spec);
+    let game_spec: GameSpecification = serde_json::from_reader(File::open(&opts.filename)?)?;
+    let mut graph_generator = GraphGenerator::new(game_spec)?;
 
-    let (_, edges) = graph_generator.generate()?;
+    if let Some(search) = opts.search {
+        let solution = match search {
+            SearchMode::Bfs => graph_generator.solve_bfs()?,
+            SearchMode::Astar => graph_generator.solve_astar()?,
+        };
+
+        match solution {
+            Some(moves) => {
+                for (piece_idx, direction) in &moves {
+                    println!("{},{:?}", piece_idx, direction);
+                }
+                println!("Solved in {} move(s)", moves.len());
+            }
+            None => println!("No solution found"),
+        }
+
+        return Ok(());
+    }
+
+    let graph_data = if let Some(load_path) = &opts.load {
+        GraphData::load(File::open(load_path)?)?
+    } else {
+        if opts.parallel {
+            graph_generator.generate_parallel()?;
+        } else {
+            graph_generator.generate()?;
+        }
+
+        graph_generator.graph_data()
+    };
+
+    if let Some(save_path) = &opts.save {
+        graph_data.save(File::create(save_path)?)?;
+    }
 
-    for edge in edges {
-        println!("{},{}", edge.0, edge.1);
+    match opts.format {
+        OutputFormat::Csv => {
+            for edge in &graph_data.edges {
+                println!("{},{}", edge.0, edge.1);
+            }
+        }
+        OutputFormat::Dot => print!("{}", graph_data.to_dot()),
+        OutputFormat::Json => graph_data.save(std::io::stdout())?,
     }
 
     Ok(())